@@ -25,7 +25,7 @@ impl Vec3 {
     }
 
     /// The dot product between two vectors
-    pub fn dot(self, other: Self) -> f64 {
+    pub fn dot(self, other: &Self) -> f64 {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
@@ -39,7 +39,26 @@ impl Vec3 {
     /// Why care about the squared length? It's slightly faster to compute,
     /// and can often be as useful as the distance itself.
     pub fn len2(self) -> f64 {
-        self.dot(self)
+        self.dot(&self)
+    }
+
+    /// The cross product between two vectors
+    pub fn cross(self, other: Self) -> Self {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// This vector, scaled to have a length of 1
+    pub fn normalize(self) -> Self {
+        self / self.len()
+    }
+
+    /// Reflect this vector across a surface with a given normal
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (2.0 * self.dot(&normal))
     }
 }
 
@@ -142,10 +161,10 @@ impl ops::SubAssign for Vec3 {
 /// is another difference. Basically, we can only do interesting things to points
 /// by the means of a vector, whereas a vector is "self-sufficient" in some sense.
 #[derive(Copy, Clone, Debug, PartialEq)]
-struct Point3 {
-    x: f64,
-    y: f64,
-    z: f64,
+pub struct Point3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
 }
 
 impl Point3 {
@@ -155,16 +174,19 @@ impl Point3 {
     }
 
     /// The point at the origin of the 3D space
+    #[allow(dead_code)]
     pub fn origin() -> Self {
         Point3::new(0.0, 0.0, 0.0)
     }
 
     /// The distance from this point to another
+    #[allow(dead_code)]
     pub fn dist(self, to: Point3) -> f64 {
         (self - to).len()
     }
 
     /// The squared distance from this point to another
+    #[allow(dead_code)]
     pub fn dist2(self, to: Point3) -> f64 {
         (self - to).len2()
     }
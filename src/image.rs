@@ -1,17 +1,16 @@
-#[cfg(feature = "png")]
-use png;
 use std::io;
 
 /// Represents a pixel in RGBA, in floating point terms.
 ///
 /// This is more useful for ray tracing itself, and can easily be converted to the final
 /// image pixel type.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, Debug)]
 pub struct FRGBA {
-    r: f64,
-    g: f64,
-    b: f64,
-    a: f64,
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
 }
 
 impl FRGBA {
@@ -26,13 +25,7 @@ impl FRGBA {
 }
 
 fn clamp(f: f64) -> f64 {
-    if f > 1.0 {
-        1.0
-    } else if f < 0.0 {
-        0.0
-    } else {
-        f
-    }
+    f.clamp(0.0, 1.0)
 }
 
 /// Create a new FRGBA color with full opacity
@@ -48,6 +41,7 @@ pub fn frgb(r: f64, g: f64, b: f64) -> FRGBA {
 /// Represents an RGBA color / pixel
 ///
 /// This is our main representation of colors, and a pretty simple struct as well
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, Debug)]
 pub struct RGBA {
     r: u8,
@@ -140,7 +134,7 @@ impl Image {
     #[cfg(feature = "png")]
     pub fn write_png<W: io::Write>(&self, w: W) -> Result<(), png::EncodingError> {
         let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
-        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_color(png::ColorType::Rgba);
         encoder.set_depth(png::BitDepth::Eight);
         let mut writer = encoder.write_header()?;
         writer.write_image_data(&self.data)
@@ -1,7 +1,9 @@
 mod geometry;
 mod image;
+mod scene;
 mod tracer;
 
+use std::env;
 use std::fs::File;
 use std::io;
 use std::path::Path;
@@ -9,11 +11,29 @@ use std::path::Path;
 extern crate png;
 extern crate fastrand;
 
-fn main() -> io::Result<()> {
-    let width = 400;
-    let height: usize = 400 / 16 * 9;
+const WIDTH: usize = 400;
+const SAMPLES_PER_PIXEL: i32 = 50;
+const THREADS: usize = 8;
 
-    let img = tracer::trace(width, height);
+fn main() -> io::Result<()> {
+    #[cfg_attr(feature = "png", allow(unused_mut))]
+    let mut img = match env::args().nth(1) {
+        Some(path) => {
+            let scene = scene::load(Path::new(&path))?;
+            let seed = fastrand::u64(..);
+            tracer::render(
+                scene.world,
+                scene.camera,
+                scene.aspect,
+                WIDTH,
+                SAMPLES_PER_PIXEL,
+                THREADS,
+                seed,
+                true,
+            )
+        }
+        None => tracer::trace(WIDTH),
+    };
 
     #[cfg(feature = "png")]
     {
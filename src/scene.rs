@@ -0,0 +1,211 @@
+use crate::geometry::{Point3, Vec3};
+use crate::image::frgb;
+use crate::tracer::{CameraParams, HittableList, Material, Sphere, Triangle};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A scene loaded from a text description: the objects to render, the camera to render
+/// them with, and the aspect ratio the scene was authored for
+pub(crate) struct Scene {
+    pub(crate) world: HittableList,
+    pub(crate) camera: CameraParams,
+    pub(crate) aspect: f64,
+}
+
+fn scene_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn parse_f64(tokens: &[&str], index: usize) -> io::Result<f64> {
+    let token = tokens
+        .get(index)
+        .ok_or_else(|| scene_error(format!("expected a number at position {}", index)))?;
+    token
+        .parse()
+        .map_err(|_| scene_error(format!("expected a number, got '{}'", token)))
+}
+
+fn parse_point3(tokens: &[&str], index: usize) -> io::Result<Point3> {
+    Ok(Point3::new(
+        parse_f64(tokens, index)?,
+        parse_f64(tokens, index + 1)?,
+        parse_f64(tokens, index + 2)?,
+    ))
+}
+
+fn parse_vec3(tokens: &[&str], index: usize) -> io::Result<Vec3> {
+    Ok(Vec3::new(
+        parse_f64(tokens, index)?,
+        parse_f64(tokens, index + 1)?,
+        parse_f64(tokens, index + 2)?,
+    ))
+}
+
+/// Parse a material specification starting at `tokens[index]`
+///
+/// Accepted forms:
+/// - `diffuse r g b`
+/// - `metal r g b fuzz`
+/// - `glass refraction_index`
+/// - `emissive r g b intensity`
+fn parse_material(tokens: &[&str], index: usize) -> io::Result<Material> {
+    let kind = tokens
+        .get(index)
+        .ok_or_else(|| scene_error("expected a material"))?;
+    match *kind {
+        "diffuse" => {
+            let color = frgb(
+                parse_f64(tokens, index + 1)?,
+                parse_f64(tokens, index + 2)?,
+                parse_f64(tokens, index + 3)?,
+            );
+            Ok(Material::Diffuse(color))
+        }
+        "metal" => {
+            let color = frgb(
+                parse_f64(tokens, index + 1)?,
+                parse_f64(tokens, index + 2)?,
+                parse_f64(tokens, index + 3)?,
+            );
+            let fuzz = parse_f64(tokens, index + 4)?;
+            Ok(Material::Metal(color, fuzz))
+        }
+        "glass" => {
+            let refraction_index = parse_f64(tokens, index + 1)?;
+            Ok(Material::Glass(refraction_index))
+        }
+        "emissive" => {
+            let color = frgb(
+                parse_f64(tokens, index + 1)?,
+                parse_f64(tokens, index + 2)?,
+                parse_f64(tokens, index + 3)?,
+            );
+            let intensity = parse_f64(tokens, index + 4)?;
+            Ok(Material::Emissive(color, intensity))
+        }
+        other => Err(scene_error(format!("unknown material kind '{}'", other))),
+    }
+}
+
+fn parse_camera(tokens: &[&str]) -> io::Result<CameraParams> {
+    Ok(CameraParams {
+        lookfrom: parse_point3(tokens, 1)?,
+        lookat: parse_point3(tokens, 4)?,
+        vup: parse_vec3(tokens, 7)?,
+        vfov_degrees: parse_f64(tokens, 10)?,
+        aperture: parse_f64(tokens, 11)?,
+        focus_dist: parse_f64(tokens, 12)?,
+        time0: parse_f64(tokens, 13)?,
+        time1: parse_f64(tokens, 14)?,
+    })
+}
+
+fn parse_sphere(tokens: &[&str]) -> io::Result<Sphere> {
+    Ok(Sphere {
+        center: parse_point3(tokens, 1)?,
+        radius: parse_f64(tokens, 4)?,
+        material: parse_material(tokens, 5)?,
+    })
+}
+
+fn parse_triangle(tokens: &[&str]) -> io::Result<Triangle> {
+    Ok(Triangle {
+        a: parse_point3(tokens, 1)?,
+        b: parse_point3(tokens, 4)?,
+        c: parse_point3(tokens, 7)?,
+        material: parse_material(tokens, 10)?,
+    })
+}
+
+/// Load a scene from a text file
+///
+/// The format is line-oriented: blank lines and lines starting with `#` are ignored,
+/// and every other line starts with a keyword (`camera`, `aspect`, `sphere`, or
+/// `triangle`) followed by its whitespace-separated arguments. A `camera` line is
+/// required (if there are several, the last one is used); everything else is optional.
+pub(crate) fn load(path: &Path) -> io::Result<Scene> {
+    let contents = fs::read_to_string(path)?;
+
+    let mut camera = None;
+    let mut aspect = 16.0 / 9.0;
+    let mut world = HittableList::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens[0] {
+            "camera" => {
+                camera = Some(parse_camera(&tokens)?);
+            }
+            "aspect" => {
+                aspect = parse_f64(&tokens, 1)?;
+            }
+            "sphere" => {
+                world.add(parse_sphere(&tokens)?);
+            }
+            "triangle" => {
+                world.add(parse_triangle(&tokens)?);
+            }
+            other => return Err(scene_error(format!("unknown line kind '{}'", other))),
+        }
+    }
+
+    let camera = camera.ok_or_else(|| scene_error("scene has no 'camera' line"))?;
+    Ok(Scene {
+        world,
+        camera,
+        aspect,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tracer::Hittable;
+    use std::io::Write;
+
+    fn load_str(contents: &str) -> io::Result<Scene> {
+        let mut path = std::env::temp_dir();
+        path.push(format!("manta-scene-test-{}.txt", fastrand::u64(..)));
+        let mut file = fs::File::create(&path)?;
+        file.write_all(contents.as_bytes())?;
+        let result = load(&path);
+        fs::remove_file(&path)?;
+        result
+    }
+
+    #[test]
+    fn test_load_camera_only_scene() {
+        let scene = load_str("camera 0 0 0 0 0 -1 0 1 0 90 0 1 0 1\n").unwrap();
+        assert_eq!(scene.camera.lookfrom, Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(scene.aspect, 16.0 / 9.0);
+    }
+
+    #[test]
+    fn test_load_scene_with_sphere_and_triangle() {
+        let scene = load_str(
+            "# a comment\n\
+             camera 0 0 0 0 0 -1 0 1 0 90 0 1 0 1\n\
+             aspect 1.5\n\
+             sphere 0 0 -1 0.5 diffuse 0.5 0.5 0.5\n\
+             triangle -1 -1 -1 1 -1 -1 0 1 -1 metal 0.8 0.8 0.8 0.1\n",
+        )
+        .unwrap();
+        assert_eq!(scene.aspect, 1.5);
+        assert!(scene.world.bounding_box(0.0, 1.0).is_some());
+    }
+
+    #[test]
+    fn test_load_scene_without_camera_fails() {
+        assert!(load_str("sphere 0 0 -1 0.5 diffuse 0.5 0.5 0.5\n").is_err());
+    }
+
+    #[test]
+    fn test_load_scene_with_malformed_token_fails_gracefully() {
+        assert!(load_str("camera 0 0 0 0 0 -1 0 1 0 90 0 1 0 not-a-number\n").is_err());
+    }
+}
@@ -1,24 +1,45 @@
 use crate::geometry::{Point3, Vec3};
 use crate::image::{frgb, Image, FRGBA};
-use fastrand;
 use std::f64::consts::PI;
 
-fn rand_range(min: f64, max: f64) -> f64 {
-    min + (max - min) * fastrand::f64()
+fn rand_range(rng: &fastrand::Rng, min: f64, max: f64) -> f64 {
+    min + (max - min) * rng.f64()
 }
 
-fn unit_rand() -> Vec3 {
-    let a = rand_range(0.0, 2.0 * PI);
-    let z = rand_range(-1.0, 1.0);
+fn unit_rand(rng: &fastrand::Rng) -> Vec3 {
+    let a = rand_range(rng, 0.0, 2.0 * PI);
+    let z = rand_range(rng, -1.0, 1.0);
     let r = (1.0 - z * z).sqrt();
     Vec3::new(r * a.cos(), r * a.sin(), z)
 }
 
+/// Pick a random point inside the unit disk, for lens sampling
+fn random_in_unit_disk(rng: &fastrand::Rng) -> Vec3 {
+    loop {
+        let p = Vec3::new(rand_range(rng, -1.0, 1.0), rand_range(rng, -1.0, 1.0), 0.0);
+        if p.len2() < 1.0 {
+            return p;
+        }
+    }
+}
+
+/// Derive a deterministic RNG seed for a single pixel from a base seed
+///
+/// Rendering is parallelized across rows, so each pixel needs its own seed rather than
+/// sharing one global RNG: this keeps the output reproducible regardless of how work is
+/// split across threads, while still giving every pixel an independent random stream.
+fn pixel_seed(seed: u64, x: usize, y: usize) -> u64 {
+    let rng = fastrand::Rng::with_seed(seed ^ ((x as u64) << 32) ^ (y as u64));
+    rng.u64(..)
+}
+
 /// Represents a ray of light moving along a certain line
 #[derive(Clone, Copy, Debug)]
-struct Ray {
+pub(crate) struct Ray {
     origin: Point3,
     direction: Vec3,
+    /// The instant in time this ray was cast, used by objects that move over time
+    time: f64,
 }
 
 impl Ray {
@@ -36,10 +57,7 @@ impl Ray {
 /// size, and what we can see, etc.
 #[derive(Copy, Clone, Debug)]
 struct Camera {
-    /// We store the aspect ratio, because it's convenient, even though it can be
-    /// derived from other properties.
-    aspect: f64,
-    /// The origin, which should be 0, 0, 0
+    /// The origin of every ray cast through the lens, before defocus offsetting
     origin: Point3,
     /// The lower left point of the image
     lower_left: Point3,
@@ -47,34 +65,74 @@ struct Camera {
     horizontal: Vec3,
     /// A vector bringing us across the height of the image
     vertical: Vec3,
+    /// The basis vector pointing right, from the camera's perspective
+    u: Vec3,
+    /// The basis vector pointing up, from the camera's perspective
+    v: Vec3,
+    /// Half the width of the aperture, used to scale defocus blur
+    lens_radius: f64,
+    /// The time at which the shutter opens
+    time0: f64,
+    /// The time at which the shutter closes
+    time1: f64,
 }
 
 const ASPECT: f64 = 16.0 / 9.0;
-const VIEW_HEIGHT: f64 = 2.0;
-const VIEW_WIDTH: f64 = ASPECT * VIEW_HEIGHT;
-const FOCAL_LENGTH: f64 = 1.0;
 
 impl Camera {
-    fn new() -> Self {
-        let origin = Vec3::new(0.0, 0.0, 0.0);
-        let horizontal = Vec3::new(VIEW_WIDTH, 0.0, 0.0);
-        let vertical = Vec3::new(0.0, VIEW_HEIGHT, 0.0);
-        let lower_left =
-            origin - horizontal / 2.0 - vertical / 2.0 - Vec3::new(0.0, 0.0, FOCAL_LENGTH);
+    /// Build a camera looking from `lookfrom` towards `lookat`.
+    ///
+    /// `vfov_degrees` is the vertical field of view, in degrees. `aperture` and
+    /// `focus_dist` control depth-of-field: objects at `focus_dist` from `lookfrom`
+    /// are in perfect focus, and a larger `aperture` blurs everything else more.
+    /// An `aperture` of 0 gives a pinhole camera with no depth-of-field at all.
+    #[allow(clippy::too_many_arguments)]
+    fn look_at(
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: Vec3,
+        vfov_degrees: f64,
+        aspect: f64,
+        aperture: f64,
+        focus_dist: f64,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        let h = (vfov_degrees.to_radians() / 2.0).tan();
+        let viewport_height = 2.0 * h;
+        let viewport_width = aspect * viewport_height;
+
+        let w = (lookfrom - lookat).normalize();
+        let u = vup.cross(w).normalize();
+        let v = w.cross(u);
+
+        let origin = lookfrom;
+        let horizontal = focus_dist * viewport_width * u;
+        let vertical = focus_dist * viewport_height * v;
+        let lower_left = origin - horizontal / 2.0 - vertical / 2.0 - focus_dist * w;
 
         Camera {
-            aspect: ASPECT,
             origin,
             lower_left,
             horizontal,
             vertical,
+            u,
+            v,
+            lens_radius: aperture / 2.0,
+            time0,
+            time1,
         }
     }
 
-    fn get_ray(&self, u: f64, v: f64) -> Ray {
+    fn get_ray(&self, rng: &fastrand::Rng, s: f64, t: f64) -> Ray {
+        let rd = self.lens_radius * random_in_unit_disk(rng);
+        let offset = self.u * rd.x + self.v * rd.y;
         Ray {
-            origin: self.origin,
-            direction: self.lower_left + self.horizontal * u + self.vertical * v - self.origin,
+            origin: self.origin + offset,
+            direction: self.lower_left + self.horizontal * s + self.vertical * t
+                - self.origin
+                - offset,
+            time: rand_range(rng, self.time0, self.time1),
         }
     }
 }
@@ -96,15 +154,20 @@ fn schlick(cosine: f64, ref_idx: f64) -> f64 {
 ///
 /// Allows us to distinguish between metals and matte materials, and what not.
 #[derive(Copy, Clone, Debug)]
-enum Material {
+pub(crate) enum Material {
     Diffuse(FRGBA),
     Metal(FRGBA, f64),
     Glass(f64),
+    /// A material that emits light instead of scattering it, scaled by an intensity
+    ///
+    /// The intensity is separate from the color so that a light can be brighter than
+    /// the `[0, 1]` range `FRGBA` components are otherwise clamped to.
+    Emissive(FRGBA, f64),
 }
 
 /// Represents the information we have after hitting a certain point.
 #[derive(Copy, Clone, Debug)]
-struct HitRecord {
+pub(crate) struct HitRecord {
     /// The point we hit
     p: Point3,
     /// The parameter to the ray equation at this point
@@ -118,7 +181,7 @@ struct HitRecord {
 }
 
 impl HitRecord {
-    fn new(t: f64, p: Vec3, ray: &Ray, out_normal: Vec3, material: Material) -> Self {
+    fn new(t: f64, p: Point3, ray: &Ray, out_normal: Vec3, material: Material) -> Self {
         let outwards = ray.direction.dot(&out_normal) < 0.0;
         let normal = if outwards { out_normal } else { -out_normal };
         HitRecord {
@@ -130,23 +193,38 @@ impl HitRecord {
         }
     }
 
-    fn scatter(&self, ray: &Ray) -> Option<(Ray, FRGBA)> {
+    /// The light this point emits towards the camera, independent of any scattering
+    fn emitted(&self) -> FRGBA {
+        match self.material {
+            Material::Emissive(color, intensity) => FRGBA {
+                r: color.r * intensity,
+                g: color.g * intensity,
+                b: color.b * intensity,
+                a: 1.0,
+            },
+            _ => frgb(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn scatter(&self, rng: &fastrand::Rng, ray: &Ray) -> Option<(Ray, FRGBA)> {
         match self.material {
             Material::Diffuse(albedo) => {
-                let direction = self.normal + unit_rand();
+                let direction = self.normal + unit_rand(rng);
                 let scattered = Ray {
                     origin: self.p,
                     direction,
+                    time: ray.time,
                 };
                 let attenuation = albedo;
                 Some((scattered, attenuation))
             }
             Material::Metal(albedo, fuzz) => {
                 let reflected = ray.direction.reflect(self.normal);
-                let direction = reflected + unit_rand() * fuzz;
+                let direction = reflected + unit_rand(rng) * fuzz;
                 let scattered = Ray {
                     origin: self.p,
                     direction,
+                    time: ray.time,
                 };
                 let attenuation = albedo;
                 if scattered.direction.dot(&self.normal) > 0.0 {
@@ -166,11 +244,12 @@ impl HitRecord {
                 }
                 let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
                 let reflect_prob = schlick(cos_theta, ri);
-                if ri_over_rt * sin_theta > 1.0 || fastrand::f64() < reflect_prob {
+                if ri_over_rt * sin_theta > 1.0 || rng.f64() < reflect_prob {
                     let reflected = unit.reflect(self.normal);
                     let scattered = Ray {
                         origin: self.p,
                         direction: reflected,
+                        time: ray.time,
                     };
                     Some((scattered, attenuation))
                 } else {
@@ -178,33 +257,110 @@ impl HitRecord {
                     let scattered = Ray {
                         origin: self.p,
                         direction: refracted,
+                        time: ray.time,
                     };
                     Some((scattered, attenuation))
                 }
             }
+            Material::Emissive(..) => None,
         }
     }
 }
 
-trait Hittable {
+pub(crate) trait Hittable: Sync {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+
+    /// The bounding box containing this object over the full shutter interval
+    ///
+    /// Returns `None` for objects that have no well-defined bounds (e.g. an empty
+    /// collection), so that callers can decide how to handle that case themselves.
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb>;
+}
+
+/// Axis-aligned bounding box, used to cheaply rule out objects a ray can't hit
+///
+/// Represented as the two opposite corners with the smallest and largest coordinates.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Aabb {
+    min: Point3,
+    max: Point3,
+}
+
+/// The amount we pad every box by on each side, along each axis.
+///
+/// Without this, an object that's flat along some axis (e.g. a triangle, or a sphere
+/// squashed into a plane) would produce a box with zero thickness there, and the slab
+/// test below can spuriously miss rays that graze exactly along that axis.
+const AABB_PADDING: f64 = 0.0001;
+
+impl Aabb {
+    fn new(a: Point3, b: Point3) -> Self {
+        let pad = Vec3::new(AABB_PADDING, AABB_PADDING, AABB_PADDING);
+        let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)) - pad;
+        let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)) + pad;
+        Aabb { min, max }
+    }
+
+    /// The smallest box containing both `self` and `other`
+    fn union(self, other: Self) -> Self {
+        let min = Point3::new(
+            self.min.x.min(other.min.x),
+            self.min.y.min(other.min.y),
+            self.min.z.min(other.min.z),
+        );
+        let max = Point3::new(
+            self.max.x.max(other.max.x),
+            self.max.y.max(other.max.y),
+            self.max.z.max(other.max.z),
+        );
+        Aabb { min, max }
+    }
+
+    fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let (origin, direction, min, max) = match axis {
+                0 => (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+                1 => (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+                _ => (ray.origin.z, ray.direction.z, self.min.z, self.max.z),
+            };
+            let inv_d = 1.0 / direction;
+            let mut t0 = (min - origin) * inv_d;
+            let mut t1 = (max - origin) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 /// Represents a list of hittable objects
-struct HittableList {
+pub(crate) struct HittableList {
     hittables: Vec<Box<dyn Hittable>>,
 }
 
 impl HittableList {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         HittableList {
             hittables: Vec::new(),
         }
     }
 
-    fn add<H: Hittable + 'static>(&mut self, hittable: H) {
+    pub(crate) fn add<H: Hittable + 'static>(&mut self, hittable: H) {
         self.hittables.push(Box::new(hittable));
     }
+
+    /// Turn this list into an equivalent BVH, to accelerate `hit`
+    ///
+    /// An empty list has no bounding box, and becomes a `Hittable` that is never hit.
+    pub(crate) fn build_bvh(self) -> Box<dyn Hittable> {
+        BvhNode::build(self.hittables, 0.0, 1.0)
+    }
 }
 
 impl Hittable for HittableList {
@@ -220,12 +376,112 @@ impl Hittable for HittableList {
         }
         res
     }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.hittables
+            .iter()
+            .try_fold(None, |acc: Option<Aabb>, obj| {
+                let bbox = obj.bounding_box(time0, time1)?;
+                Some(Some(match acc {
+                    Some(running) => running.union(bbox),
+                    None => bbox,
+                }))
+            })
+            .flatten()
+    }
 }
 
-struct Sphere {
-    center: Point3,
-    radius: f64,
-    material: Material,
+/// A `Hittable` with no objects and no bounding box, used as the accelerated form of an
+/// empty `HittableList`
+struct EmptyHittable;
+
+impl Hittable for EmptyHittable {
+    fn hit(&self, _ray: &Ray, _t_min: f64, _t_max: f64) -> Option<HitRecord> {
+        None
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        None
+    }
+}
+
+/// A node in a bounding-volume hierarchy over a fixed set of hittables
+///
+/// Splitting along a randomly chosen axis, and sorting by that axis's
+/// minimum coordinate, is a simple heuristic that works well enough in practice without
+/// needing a more sophisticated partitioning scheme.
+struct BvhNode {
+    left: Box<dyn Hittable>,
+    right: Box<dyn Hittable>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    /// Build a BVH (or an equivalent leaf) out of a list of hittables
+    ///
+    /// An empty `hittables` yields an `EmptyHittable` rather than panicking, since a
+    /// scene with no objects is a valid (if uninteresting) thing to render.
+    fn build(mut hittables: Vec<Box<dyn Hittable>>, time0: f64, time1: f64) -> Box<dyn Hittable> {
+        match hittables.len() {
+            0 => Box::new(EmptyHittable),
+            1 => hittables.pop().unwrap(),
+            _ => {
+                let axis = fastrand::usize(0..3);
+                hittables.sort_by(|a, b| {
+                    let box_a = a
+                        .bounding_box(time0, time1)
+                        .expect("hittable in BVH has no bounding box");
+                    let box_b = b
+                        .bounding_box(time0, time1)
+                        .expect("hittable in BVH has no bounding box");
+                    let coord = |b: Aabb| match axis {
+                        0 => b.min.x,
+                        1 => b.min.y,
+                        _ => b.min.z,
+                    };
+                    coord(box_a).partial_cmp(&coord(box_b)).unwrap()
+                });
+                let mid = hittables.len() / 2;
+                let right_half = hittables.split_off(mid);
+                let left = BvhNode::build(hittables, time0, time1);
+                let right = BvhNode::build(right_half, time0, time1);
+                let bbox = left
+                    .bounding_box(time0, time1)
+                    .expect("hittable in BVH has no bounding box")
+                    .union(
+                        right
+                            .bounding_box(time0, time1)
+                            .expect("hittable in BVH has no bounding box"),
+                    );
+                Box::new(BvhNode { left, right, bbox })
+            }
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return None;
+        }
+        match self.left.hit(ray, t_min, t_max) {
+            Some(left_rec) => {
+                let right_rec = self.right.hit(ray, t_min, left_rec.t);
+                Some(right_rec.unwrap_or(left_rec))
+            }
+            None => self.right.hit(ray, t_min, t_max),
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
+
+pub(crate) struct Sphere {
+    pub(crate) center: Point3,
+    pub(crate) radius: f64,
+    pub(crate) material: Material,
 }
 
 impl Hittable for Sphere {
@@ -258,26 +514,185 @@ impl Hittable for Sphere {
             }
         }
     }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - radius, self.center + radius))
+    }
+}
+
+/// A sphere that linearly interpolates between two centers over a time interval
+///
+/// Outside of `[time0, time1]` the center is simply extrapolated along the same line,
+/// which is fine since rays are only ever cast with times inside the shutter interval.
+struct MovingSphere {
+    center0: Point3,
+    center1: Point3,
+    time0: f64,
+    time1: f64,
+    radius: f64,
+    material: Material,
+}
+
+impl MovingSphere {
+    fn center(&self, time: f64) -> Point3 {
+        self.center0
+            + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let center = self.center(ray.time);
+        let oc = ray.origin - center;
+        let a = ray.direction.len2();
+        let half_b = oc.dot(&ray.direction);
+        let c = oc.len2() - self.radius * self.radius;
+        let discrim = half_b * half_b - a * c;
+
+        if discrim < 0.0 {
+            None
+        } else {
+            let root = discrim.sqrt();
+            let get_record = |solution| {
+                let t = solution;
+                let p = ray.at(t);
+                let normal = (p - center) / self.radius;
+                HitRecord::new(t, p, ray, normal, self.material)
+            };
+            let valid_range = t_min..t_max;
+            let solution1 = (-half_b - root) / a;
+            let solution2 = (-half_b + root) / a;
+            if valid_range.contains(&solution1) {
+                Some(get_record(solution1))
+            } else if valid_range.contains(&solution2) {
+                Some(get_record(solution2))
+            } else {
+                None
+            }
+        }
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius);
+        let box0 = Aabb::new(self.center(time0) - radius, self.center(time0) + radius);
+        let box1 = Aabb::new(self.center(time1) - radius, self.center(time1) + radius);
+        Some(box0.union(box1))
+    }
+}
+
+/// A flat triangle with vertices `a`, `b`, `c`, in counter-clockwise winding order
+pub(crate) struct Triangle {
+    pub(crate) a: Point3,
+    pub(crate) b: Point3,
+    pub(crate) c: Point3,
+    pub(crate) material: Material,
+}
+
+/// The smallest `|det|` we'll divide by, below which the ray is treated as parallel to
+/// the triangle's plane (and therefore a miss) rather than risking a near-infinite `t`.
+const TRIANGLE_EPSILON: f64 = 1e-8;
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Moller-Trumbore ray-triangle intersection
+        let e1 = self.b - self.a;
+        let e2 = self.c - self.a;
+        let p = ray.direction.cross(e2);
+        let det = e1.dot(&p);
+        if det.abs() < TRIANGLE_EPSILON {
+            return None;
+        }
+        let inv = 1.0 / det;
+
+        let tvec = ray.origin - self.a;
+        let u = tvec.dot(&p) * inv;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = tvec.cross(e1);
+        let v = ray.direction.dot(&q) * inv;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = e2.dot(&q) * inv;
+        if !(t_min..t_max).contains(&t) {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let normal = e1.cross(e2).normalize();
+        Some(HitRecord::new(t, p, ray, normal, self.material))
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(Aabb::new(self.a, self.b).union(Aabb::new(self.a, self.c)))
+    }
+}
+
+/// The color a ray that never hits anything sees
+///
+/// `use_sky` selects between the usual sky gradient and flat black, which lets a scene
+/// be lit purely by its own `Emissive` objects (an enclosed, dark room with a light
+/// inside, say).
+fn background_color(ray: &Ray, use_sky: bool) -> FRGBA {
+    if use_sky {
+        let unit = ray.direction.normalize();
+        let t = 0.5 * (unit.y + 1.0);
+        frgb(1.0, 1.0, 1.0).lerp(t, frgb(0.5, 0.7, 1.0))
+    } else {
+        frgb(0.0, 0.0, 0.0)
+    }
 }
 
-fn ray_color(mut ray: Ray, world: &dyn Hittable, depth: i32) -> FRGBA {
-    let mut color = frgb(1.0, 1.0, 1.0);
+/// Trace a ray through the scene, accumulating `emitted + attenuation * recurse(...)`
+/// at each bounce
+///
+/// A ray that hits an emitter terminates immediately with the light it contributed so
+/// far; one that escapes the scene picks up the background color, attenuated by
+/// whatever it bounced off along the way.
+fn ray_color(
+    rng: &fastrand::Rng,
+    mut ray: Ray,
+    world: &dyn Hittable,
+    depth: i32,
+    use_sky: bool,
+) -> FRGBA {
+    let mut emitted_acc = FRGBA {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+    let mut attenuation_acc = frgb(1.0, 1.0, 1.0);
     for _ in 0..depth {
         if let Some(rec) = world.hit(&ray, 0.0001, f64::INFINITY) {
-            if let Some((scattered, attenuation)) = rec.scatter(&ray) {
-                ray = scattered;
-                color.r *= attenuation.r;
-                color.g *= attenuation.g;
-                color.b *= attenuation.b;
+            let emitted = rec.emitted();
+            emitted_acc.r += attenuation_acc.r * emitted.r;
+            emitted_acc.g += attenuation_acc.g * emitted.g;
+            emitted_acc.b += attenuation_acc.b * emitted.b;
+            match rec.scatter(rng, &ray) {
+                Some((scattered, attenuation)) => {
+                    ray = scattered;
+                    attenuation_acc.r *= attenuation.r;
+                    attenuation_acc.g *= attenuation.g;
+                    attenuation_acc.b *= attenuation.b;
+                }
+                None => return emitted_acc,
             }
         } else {
-            let unit = ray.direction.normalize();
-            let t = 0.5 * (unit.y + 1.0);
-            let base = frgb(1.0, 1.0, 1.0).lerp(t, frgb(0.5, 0.7, 1.0));
-            return frgb(base.r * color.r, base.g * color.g, base.b * color.b);
+            let background = background_color(&ray, use_sky);
+            return FRGBA {
+                r: emitted_acc.r + attenuation_acc.r * background.r,
+                g: emitted_acc.g + attenuation_acc.g * background.g,
+                b: emitted_acc.b + attenuation_acc.b * background.b,
+                a: 1.0,
+            };
         }
     }
-    frgb(0.0, 0.0, 0.0)
+    emitted_acc
 }
 
 /// A struct allowing us to add color samples, and end up with a final mixed color
@@ -323,47 +738,314 @@ impl SampledColor {
 
 const SAMPLES_PER_PIXEL: i32 = 50;
 const MAX_DEPTH: i32 = 50;
+const THREADS: usize = 8;
 
-pub fn trace(width: usize) -> Image {
-    let height = ((width as f64) / ASPECT) as usize;
+/// Render pixel data for rows `y_start..y_end`, returning one `FRGBA` per pixel in
+/// row-major order
+///
+/// Splitting rendering up by row, rather than sharing a single RNG across the whole
+/// image, is what lets `render` hand different row ranges to different threads while
+/// still producing a deterministic image for a given `seed`.
+#[allow(clippy::too_many_arguments)]
+fn render_rows(
+    world: &(dyn Hittable + Sync),
+    camera: &Camera,
+    width: usize,
+    height: usize,
+    samples: i32,
+    seed: u64,
+    use_sky: bool,
+    y_start: usize,
+    y_end: usize,
+) -> Vec<FRGBA> {
+    let mut pixels = Vec::with_capacity((y_end - y_start) * width);
+    for y in y_start..y_end {
+        for x in 0..width {
+            let rng = fastrand::Rng::with_seed(pixel_seed(seed, x, y));
+            let mut sampled = SampledColor::empty();
+            for _ in 0..samples {
+                let u = (rng.f64() + x as f64) / (width - 1) as f64;
+                let v = 1.0 - (y as f64 - rng.f64()) / (height - 1) as f64;
+                let ray = camera.get_ray(&rng, u, v);
+                sampled.add(ray_color(&rng, ray, world, MAX_DEPTH, use_sky));
+            }
+            pixels.push(sampled.result());
+        }
+        println!("line {} / {}", y + 1, height);
+    }
+    pixels
+}
+
+/// The parameters needed to build a `Camera`, grouped so that callers threading a scene
+/// through `render` don't need to pass each one individually
+pub(crate) struct CameraParams {
+    pub(crate) lookfrom: Point3,
+    pub(crate) lookat: Point3,
+    pub(crate) vup: Vec3,
+    pub(crate) vfov_degrees: f64,
+    pub(crate) aperture: f64,
+    pub(crate) focus_dist: f64,
+    pub(crate) time0: f64,
+    pub(crate) time1: f64,
+}
+
+/// Render `world` as seen by a camera built from `camera` and `aspect`
+///
+/// This is the general entry point used by both the hardcoded demo scene and scenes
+/// loaded from a file. The image is `width` pixels wide, and its height is derived from
+/// `width` and `aspect`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render(
+    world: HittableList,
+    camera: CameraParams,
+    aspect: f64,
+    width: usize,
+    samples: i32,
+    threads: usize,
+    seed: u64,
+    use_sky: bool,
+) -> Image {
+    let height = ((width as f64) / aspect) as usize;
     let mut image = Image::empty(width, height);
 
+    let world = world.build_bvh();
+    let camera = Camera::look_at(
+        camera.lookfrom,
+        camera.lookat,
+        camera.vup,
+        camera.vfov_degrees,
+        aspect,
+        camera.aperture,
+        camera.focus_dist,
+        camera.time0,
+        camera.time1,
+    );
+
+    let rows_per_thread = height.div_ceil(threads);
+    let chunks: Vec<(usize, usize, Vec<FRGBA>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let y_start = (i * rows_per_thread).min(height);
+                let y_end = (y_start + rows_per_thread).min(height);
+                let world = world.as_ref();
+                let camera = &camera;
+                scope.spawn(move || {
+                    (
+                        y_start,
+                        y_end,
+                        render_rows(
+                            world, camera, width, height, samples, seed, use_sky, y_start, y_end,
+                        ),
+                    )
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    for (y_start, _, pixels) in chunks {
+        for (i, pixel) in pixels.into_iter().enumerate() {
+            let y = y_start + i / width;
+            let x = i % width;
+            image.set(x, y, pixel);
+        }
+    }
+    image
+}
+
+pub fn trace(width: usize) -> Image {
     let mut world = HittableList::new();
     world.add(Sphere {
-        center: Vec3::new(0.0, 0.0, -1.0),
+        center: Point3::new(0.0, 0.0, -1.0),
         radius: 0.5,
         material: Material::Diffuse(frgb(0.7, 0.3, 0.3)),
     });
     world.add(Sphere {
-        center: Vec3::new(0.0, -100.5, -1.0),
+        center: Point3::new(0.0, -100.5, -1.0),
         radius: 100.0,
         material: Material::Diffuse(frgb(0.8, 0.8, 0.0)),
     });
-    world.add(Sphere {
-        center: Vec3::new(1.0, 0.0, -1.0),
+    world.add(MovingSphere {
+        center0: Point3::new(1.0, 0.0, -1.0),
+        center1: Point3::new(1.0, 0.2, -1.0),
+        time0: 0.0,
+        time1: 1.0,
         radius: 0.5,
         material: Material::Metal(frgb(0.8, 0.6, 0.2), 0.3),
     });
     world.add(Sphere {
-        center: Vec3::new(-1.0, 0.0, -1.0),
+        center: Point3::new(-1.0, 0.0, -1.0),
         radius: 0.5,
         material: Material::Glass(1.5),
     });
+    world.add(Sphere {
+        center: Point3::new(0.0, 1.2, -1.0),
+        radius: 0.3,
+        material: Material::Emissive(frgb(1.0, 1.0, 1.0), 4.0),
+    });
 
-    let camera = Camera::new();
+    let camera = CameraParams {
+        lookfrom: Point3::new(0.0, 0.0, 0.0),
+        lookat: Point3::new(0.0, 0.0, -1.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+        vfov_degrees: 90.0,
+        aperture: 0.0,
+        focus_dist: 1.0,
+        time0: 0.0,
+        time1: 1.0,
+    };
 
-    for y in 0..height {
-        for x in 0..width {
-            let mut sampled = SampledColor::empty();
-            for _ in 0..SAMPLES_PER_PIXEL {
-                let u = (fastrand::f64() + x as f64) / (width - 1) as f64;
-                let v = 1.0 - (y as f64 - fastrand::f64()) / (height - 1) as f64;
-                let ray = camera.get_ray(u, v);
-                sampled.add(ray_color(ray, &world, MAX_DEPTH));
-            }
-            image.set(x, y, sampled.result());
+    let seed = fastrand::u64(..);
+    render(
+        world,
+        camera,
+        ASPECT,
+        width,
+        SAMPLES_PER_PIXEL,
+        THREADS,
+        seed,
+        true,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_ray(origin: Point3, direction: Vec3) -> Ray {
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
         }
-        println!("line {} / {}", y + 1, height);
     }
-    image
+
+    #[test]
+    fn test_aabb_hit_and_miss() {
+        let bbox = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let hitting = test_ray(Point3::new(-2.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(bbox.hit(&hitting, 0.0, f64::INFINITY));
+        let missing = test_ray(Point3::new(-2.0, 5.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!bbox.hit(&missing, 0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_aabb_hit_behind_origin() {
+        let bbox = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        // The box is entirely behind the ray's origin, so it shouldn't register as a hit
+        // within the ray's valid t range.
+        let ray = test_ray(Point3::new(5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!bbox.hit(&ray, 0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_aabb_hit_degenerate_box() {
+        // A box with zero thickness along the z axis shouldn't spuriously miss a ray
+        // traveling parallel to that axis.
+        let bbox = Aabb::new(Point3::new(-1.0, -1.0, 0.0), Point3::new(1.0, 1.0, 0.0));
+        let ray = test_ray(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(bbox.hit(&ray, 0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn test_build_bvh_from_empty_world_does_not_panic() {
+        let world = HittableList::new().build_bvh();
+        assert!(world.bounding_box(0.0, 1.0).is_none());
+        let ray = test_ray(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(world.hit(&ray, 0.0001, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_pixel_seed_is_reproducible() {
+        assert_eq!(pixel_seed(42, 3, 7), pixel_seed(42, 3, 7));
+    }
+
+    #[test]
+    fn test_pixel_seed_varies_with_inputs() {
+        let base = pixel_seed(42, 3, 7);
+        assert_ne!(base, pixel_seed(43, 3, 7));
+        assert_ne!(base, pixel_seed(42, 4, 7));
+        assert_ne!(base, pixel_seed(42, 3, 8));
+    }
+
+    #[test]
+    fn test_ray_color_hits_emissive_sphere_directly() {
+        let mut world = HittableList::new();
+        world.add(Sphere {
+            center: Point3::new(0.0, 0.0, -1.0),
+            radius: 0.5,
+            material: Material::Emissive(frgb(1.0, 1.0, 1.0), 4.0),
+        });
+        let rng = fastrand::Rng::with_seed(0);
+        let ray = test_ray(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let color = ray_color(&rng, ray, &world, MAX_DEPTH, false);
+        assert_eq!(color.r, 4.0);
+        assert_eq!(color.g, 4.0);
+        assert_eq!(color.b, 4.0);
+    }
+
+    #[test]
+    fn test_ray_color_attenuates_emissive_light_through_a_bounce() {
+        // A zero-fuzz metal mirror reflects deterministically, so a straight-down ray
+        // bounces straight back up into the light above it, with no randomness involved.
+        let mut world = HittableList::new();
+        world.add(Sphere {
+            center: Point3::new(0.0, -100.5, -1.0),
+            radius: 100.0,
+            material: Material::Metal(frgb(0.5, 0.5, 0.5), 0.0),
+        });
+        world.add(Sphere {
+            center: Point3::new(0.0, 1.2, -1.0),
+            radius: 0.3,
+            material: Material::Emissive(frgb(1.0, 1.0, 1.0), 4.0),
+        });
+        let rng = fastrand::Rng::with_seed(0);
+        let ray = test_ray(Point3::new(0.0, 0.5, -1.0), Vec3::new(0.0, -1.0, 0.0));
+        let color = ray_color(&rng, ray, &world, MAX_DEPTH, false);
+        // The mirror's 0.5 albedo halves the light's emitted color on the way back.
+        assert_eq!(color.r, 2.0);
+        assert_eq!(color.g, 2.0);
+        assert_eq!(color.b, 2.0);
+    }
+
+    #[test]
+    fn test_triangle_hit() {
+        let triangle = Triangle {
+            a: Point3::new(-1.0, -1.0, -1.0),
+            b: Point3::new(1.0, -1.0, -1.0),
+            c: Point3::new(0.0, 1.0, -1.0),
+            material: Material::Diffuse(frgb(1.0, 1.0, 1.0)),
+        };
+        let hitting = test_ray(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let rec = triangle.hit(&hitting, 0.0001, f64::INFINITY);
+        assert!(rec.is_some());
+        assert_eq!(rec.unwrap().t, 1.0);
+
+        let missing = test_ray(Point3::new(5.0, 5.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(triangle.hit(&missing, 0.0001, f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_triangle_hit_through_bvh_degenerate_floor() {
+        // A triangle lying flat in a plane has a zero-thickness bounding box along one
+        // axis; make sure the BVH's padding keeps it from being spuriously skipped.
+        let mut world = HittableList::new();
+        world.add(Triangle {
+            a: Point3::new(-10.0, 0.0, -10.0),
+            b: Point3::new(10.0, 0.0, -10.0),
+            c: Point3::new(0.0, 0.0, 10.0),
+            material: Material::Diffuse(frgb(1.0, 1.0, 1.0)),
+        });
+        // A second triangle in the same plane, so that `build_bvh` produces a node whose
+        // box is itself flat, instead of returning the lone triangle unwrapped.
+        world.add(Triangle {
+            a: Point3::new(20.0, 0.0, -10.0),
+            b: Point3::new(30.0, 0.0, -10.0),
+            c: Point3::new(25.0, 0.0, 10.0),
+            material: Material::Diffuse(frgb(1.0, 1.0, 1.0)),
+        });
+        let world = world.build_bvh();
+        let ray = test_ray(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(world.hit(&ray, 0.0001, f64::INFINITY).is_some());
+    }
 }